@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+/// A TMDB-queryable language/region pair. TMDB's `language` query parameter takes the
+/// same `ll-CC` form as `tmdb_code` returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Locale {
+    EnUs,
+    FrFr,
+    DeDe,
+    JaJp,
+}
+
+impl Locale {
+    pub fn tmdb_code(&self) -> &'static str {
+        match self {
+            Locale::EnUs => "en-US",
+            Locale::FrFr => "fr-FR",
+            Locale::DeDe => "de-DE",
+            Locale::JaJp => "ja-JP",
+        }
+    }
+
+    /// Best-effort match from a TMDB `original_language` code (e.g. `"en"`) to a `Locale`.
+    pub fn from_language_code(code: &str) -> Option<Self> {
+        match code {
+            "en" => Some(Locale::EnUs),
+            "fr" => Some(Locale::FrFr),
+            "de" => Some(Locale::DeDe),
+            "ja" => Some(Locale::JaJp),
+            _ => None,
+        }
+    }
+}
+
+/// Localized `title`/`overview` pair for a single `Locale`, as returned by a TMDB request
+/// made with that locale's `language` query parameter.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LocalizedText {
+    pub title: String,
+    pub overview: String,
+}
+
+/// User-configurable, ordered list of locales to try before falling back to
+/// `original_language` and then to whatever localized text happens to be present.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LocalePreferences(pub Vec<Locale>);
+
+impl LocalePreferences {
+    pub fn new(preferred: Vec<Locale>) -> Self {
+        Self(preferred)
+    }
+}
@@ -1,5 +1,7 @@
+use crate::locale::{Locale, LocalePreferences, LocalizedText};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufReader, Write};
 
@@ -14,6 +16,12 @@ pub struct Series {
     pub first_air_date: String,
     pub vote_average: f32,
     pub adult: bool,
+    // Additional name/overview pairs fetched via TMDB's per-language `language` query parameter.
+    #[serde(default)]
+    pub localized: HashMap<Locale, LocalizedText>,
+    // Average per-episode runtime in minutes, as returned by TMDB's `episode_run_time`.
+    #[serde(default)]
+    pub episode_run_time: Vec<u32>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -28,6 +36,68 @@ pub struct Movie {
     pub vote_average: f32,
     pub vote_count: u32,
     pub adult: bool,
+    // Additional title/overview pairs fetched via TMDB's per-language `language` query parameter.
+    #[serde(default)]
+    pub localized: HashMap<Locale, LocalizedText>,
+    pub runtime: Option<u32>,
+}
+
+impl Movie {
+    /// Merges one more locale's fetched title/overview into this record.
+    pub fn merge_localized(&mut self, locale: Locale, title: String, overview: String) {
+        self.localized.insert(locale, LocalizedText { title, overview });
+    }
+
+    /// Picks the title for the first locale in `preferences` that's present, then falls back
+    /// to `original_language`, then to any present localized title, then to the base `title`.
+    pub fn localized_title(&self, preferences: &LocalePreferences) -> &str {
+        for locale in &preferences.0 {
+            if let Some(text) = self.localized.get(locale) {
+                return &text.title;
+            }
+        }
+        if let Some(text) = Locale::from_language_code(&self.original_language)
+            .and_then(|locale| self.localized.get(&locale))
+        {
+            return &text.title;
+        }
+        if let Some(text) = self.localized.values().next() {
+            return &text.title;
+        }
+        &self.title
+    }
+}
+
+impl Series {
+    /// Merges one more locale's fetched name/overview into this record.
+    pub fn merge_localized(&mut self, locale: Locale, name: String, overview: String) {
+        self.localized.insert(
+            locale,
+            LocalizedText {
+                title: name,
+                overview,
+            },
+        );
+    }
+
+    /// Picks the name for the first locale in `preferences` that's present, then falls back
+    /// to `original_language`, then to any present localized name, then to the base `name`.
+    pub fn localized_name(&self, preferences: &LocalePreferences) -> &str {
+        for locale in &preferences.0 {
+            if let Some(text) = self.localized.get(locale) {
+                return &text.title;
+            }
+        }
+        if let Some(text) = Locale::from_language_code(&self.original_language)
+            .and_then(|locale| self.localized.get(&locale))
+        {
+            return &text.title;
+        }
+        if let Some(text) = self.localized.values().next() {
+            return &text.title;
+        }
+        &self.name
+    }
 }
 
 #[derive(Clone)]
@@ -50,6 +120,55 @@ pub struct ProductionIds {
     pub wikidata_id: Option<String>,
 }
 
+impl ProductionIds {
+    pub fn imdb_url(&self) -> Option<String> {
+        self.imdb_id
+            .as_ref()
+            .map(|id| format!("https://www.imdb.com/title/{}", id))
+    }
+
+    pub fn tvdb_url(&self) -> Option<String> {
+        self.tvdb_id
+            .map(|id| format!("https://thetvdb.com/dereferrer/series/{}", id))
+    }
+
+    pub fn wikidata_url(&self) -> Option<String> {
+        self.wikidata_id
+            .as_ref()
+            .map(|id| format!("https://www.wikidata.org/wiki/{}", id))
+    }
+
+    pub fn facebook_url(&self) -> Option<String> {
+        self.facebook_id
+            .as_ref()
+            .map(|id| format!("https://www.facebook.com/{}", id))
+    }
+
+    pub fn instagram_url(&self) -> Option<String> {
+        self.instagram_id
+            .as_ref()
+            .map(|id| format!("https://www.instagram.com/{}", id))
+    }
+
+    pub fn twitter_url(&self) -> Option<String> {
+        self.twitter_id
+            .as_ref()
+            .map(|id| format!("https://twitter.com/{}", id))
+    }
+}
+
+/// Source of TMDB's `/find/{external_id}` endpoint, kept as a trait so this module doesn't
+/// depend on the concrete HTTP client. Lets a pasted external id (e.g. an IMDb `tt...` id)
+/// be resolved into a `Production` as an "add by link" import path.
+pub trait ExternalIdSource {
+    fn find_by_external_id(&self, external_id: &str) -> Option<Production>;
+}
+
+/// Resolves a pasted external id (IMDb, TVDB, ...) into a `Production` via `source`.
+pub fn resolve_external_id(external_id: &str, source: &dyn ExternalIdSource) -> Option<Production> {
+    source.find_by_external_id(external_id)
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Trailer {
     pub name: String,
@@ -71,6 +190,33 @@ pub struct UserMovie {
     pub movie: Movie,
     pub user_rating: f32,
     pub note: String,
+    #[serde(default)]
+    pub watched: bool,
+    #[serde(default)]
+    pub favourite: bool,
+    // Explicit star rating (e.g. out of 5), kept separate from the free-form `user_rating`.
+    #[serde(default)]
+    pub star_rating: Option<u8>,
+    // One timestamp (unix seconds) per time this movie was marked watched; rewatch_count() is its length.
+    #[serde(default)]
+    pub watch_log: Vec<u64>,
+}
+
+impl UserMovie {
+    /// Marks the movie watched and logs the watch, so re-marking it later counts as a rewatch.
+    pub fn mark_watched(&mut self, timestamp_secs: u64) {
+        self.watched = true;
+        self.watch_log.push(timestamp_secs);
+    }
+
+    pub fn rewatch_count(&self) -> usize {
+        self.watch_log.len()
+    }
+
+    /// Total minutes spent watching, i.e. runtime times number of (re)watches.
+    pub fn watch_time_minutes(&self) -> u32 {
+        self.movie.runtime.unwrap_or(0) * self.watch_log.len() as u32
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -79,6 +225,13 @@ pub struct UserSeries {
     pub user_rating: f32,
     pub note: String,
     pub season_notes: Vec<SeasonNotes>,
+    #[serde(default)]
+    pub favourite: bool,
+    #[serde(default)]
+    pub star_rating: Option<u8>,
+    // One timestamp (unix seconds) per full-series rewatch, logged separately from per-episode watch state.
+    #[serde(default)]
+    pub watch_log: Vec<u64>,
 }
 
 impl UserSeries {
@@ -91,12 +244,85 @@ impl UserSeries {
             self.season_notes.push(SeasonNotes::new());
         }
     }
+
+    /// Fraction of tracked episodes marked watched, in `0.0..=1.0`. Zero when no
+    /// episodes have been sized in yet so a freshly added series sorts as unwatched.
+    pub fn completion_percentage(&self) -> f32 {
+        let mut watched = 0usize;
+        let mut total = 0usize;
+        for season in &self.season_notes {
+            total += season.episode_notes.len();
+            watched += season.episode_notes.iter().filter(|e| e.watched).count();
+        }
+        if total == 0 {
+            return 0.0;
+        }
+        watched as f32 / total as f32
+    }
+
+    fn watched_episode_count(&self) -> u32 {
+        self.season_notes
+            .iter()
+            .flat_map(|season| &season.episode_notes)
+            .filter(|episode| episode.watched)
+            .count() as u32
+    }
+
+    // Every sized episode, watched or not, i.e. a full pass through the series.
+    fn total_episode_count(&self) -> u32 {
+        self.season_notes
+            .iter()
+            .map(|season| season.episode_notes.len())
+            .sum::<usize>() as u32
+    }
+
+    /// Logs a full-series rewatch, counted toward `watch_time_minutes` on top of the
+    /// per-episode watch progress.
+    pub fn mark_rewatched(&mut self, timestamp_secs: u64) {
+        self.watch_log.push(timestamp_secs);
+    }
+
+    pub fn rewatch_count(&self) -> usize {
+        self.watch_log.len()
+    }
+
+    /// Total minutes spent watching: summed episode runtimes for episodes watched so far,
+    /// plus one full pass of the series (every sized episode) for each logged rewatch.
+    pub fn watch_time_minutes(&self) -> u32 {
+        if self.series.episode_run_time.is_empty() {
+            return 0;
+        }
+        let avg_episode_runtime = self.series.episode_run_time.iter().sum::<u32>()
+            / self.series.episode_run_time.len() as u32;
+        let watched_once = avg_episode_runtime * self.watched_episode_count();
+        let rewatch_minutes = avg_episode_runtime * self.total_episode_count() * self.rewatch_count() as u32;
+        watched_once + rewatch_minutes
+    }
+
+    /// The first unwatched episode in season/episode order, as 1-based `(season, episode)`.
+    pub fn next_unwatched(&self) -> Option<(usize, usize)> {
+        for (season_idx, season) in self.season_notes.iter().enumerate() {
+            for (episode_idx, episode) in season.episode_notes.iter().enumerate() {
+                if !episode.watched {
+                    return Some((season_idx + 1, episode_idx + 1));
+                }
+            }
+        }
+        None
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SeasonNotes {
     pub note: String,
-    pub episode_notes: Vec<String>,
+    #[serde(deserialize_with = "deserialize_episode_notes")]
+    pub episode_notes: Vec<EpisodeNote>,
+}
+
+impl Default for SeasonNotes {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SeasonNotes {
@@ -112,63 +338,93 @@ impl SeasonNotes {
         }
         let fill = len - self.episode_notes.len();
         for _ in 0..fill {
-            self.episode_notes.push("".into());
+            self.episode_notes.push(EpisodeNote::new());
         }
     }
 }
 
-pub fn serialize_user_productions(user_series: &[UserSeries], user_movies: &[UserMovie]) -> Result<(), String> {
-    let john = json!({
-        "series": user_series,
-        "movies": user_movies
-    });
-    let serialized_json = serde_json::to_string(&john).expect("Failed to serialize JSON");
-    let temp_path = "res/user_prod_temp.json";
-    let mut file = match File::create(temp_path) {
-        Ok(file_handle) => file_handle,
-        Err(err) => return Err(err.to_string()),
-    };
+/// Per-episode watch state. Replaces the old plain-`String` note so the central list can
+/// sort/filter by progress instead of just holding free-text notes.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EpisodeNote {
+    pub note: String,
+    pub watched: bool,
+    pub last_watched: Option<String>,
+    pub resume_position_secs: Option<u32>,
+}
 
-    if let Err(err) = file.write(serialized_json.as_bytes()) {
-        return Err(err.to_string());
+impl EpisodeNote {
+    pub fn new() -> Self {
+        Self::default()
     }
+}
 
-    // Write to a file, or write to a temp file then move files.
-    let path = "res/user_prod.json";
-    match std::fs::rename(temp_path, path) {
-        Err(err) => Err(err.to_string()),
-        Ok(_) => Ok(()),
+// Old `user_prod.json` files store episode_notes as `Vec<String>`. Accept either shape so
+// existing libraries keep loading, with the legacy note text carried over and watch state empty.
+fn deserialize_episode_notes<'de, D>(deserializer: D) -> Result<Vec<EpisodeNote>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum LegacyOrNote {
+        Legacy(String),
+        Note(EpisodeNote),
     }
+
+    let raw: Vec<LegacyOrNote> = Vec::deserialize(deserializer)?;
+    Ok(raw
+        .into_iter()
+        .map(|entry| match entry {
+            LegacyOrNote::Legacy(note) => EpisodeNote {
+                note,
+                ..Default::default()
+            },
+            LegacyOrNote::Note(episode_note) => episode_note,
+        })
+        .collect())
 }
 
-pub fn deserialize_user_productions(path: Option<String>) -> Result<(Vec<UserSeries>, Vec<UserMovie>), String> {
-    let path = match path {
-        Some(s) => s,
-        None => "res/user_prod.json".into(),
-    };
-    let file = match File::open(path) {
-        Ok(file_handle) => file_handle,
-        Err(err) => return Err(err.to_string()),
-    };
-    let reader = BufReader::new(file);
-    let mut json: Value = serde_json::from_reader(reader).expect("Failed on read from memory");
-    let series_arr = json["series"].take();
-    let movies_arr = json["movies"].take();
-    let user_series = match serde_json::from_value(series_arr) {
-        Ok(vec_value) => vec_value,
-        Err(err) => return Err(err.to_string()),
-    };
-    let user_movies = match serde_json::from_value(movies_arr) {
-        Ok(vec_value) => vec_value,
-        Err(err) => return Err(err.to_string()),
-    };
-    Ok((user_series, user_movies))
+pub const USER_PRODUCTIONS_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum PersistenceError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    UnsupportedSchemaVersion(u32),
+}
+
+impl std::fmt::Display for PersistenceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PersistenceError::Io(err) => write!(f, "io error: {}", err),
+            PersistenceError::Json(err) => write!(f, "json error: {}", err),
+            PersistenceError::UnsupportedSchemaVersion(version) => {
+                write!(f, "unsupported user_prod.json schema_version {}", version)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PersistenceError {}
+
+impl From<std::io::Error> for PersistenceError {
+    fn from(err: std::io::Error) -> Self {
+        PersistenceError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for PersistenceError {
+    fn from(err: serde_json::Error) -> Self {
+        PersistenceError::Json(err)
+    }
 }
 
 /*
 Serialization:
 user_prod.json
 {
+    "schema_version": 1,
     "series":[
         {UserSeries}
         {UserSeries}
@@ -179,10 +435,76 @@ user_prod.json
     ]
 }
 */
+#[derive(Debug, Serialize, Deserialize)]
+struct UserProductionsEnvelope {
+    schema_version: u32,
+    series: Vec<UserSeries>,
+    movies: Vec<UserMovie>,
+}
+
+pub fn serialize_user_productions(
+    user_series: &[UserSeries],
+    user_movies: &[UserMovie],
+) -> Result<(), PersistenceError> {
+    let path = "res/user_prod.json";
+    if std::path::Path::new(path).exists() {
+        let backup_secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        let backup_path = format!("res/user_prod.{}.bak.json", backup_secs);
+        std::fs::copy(path, backup_path)?;
+    }
+
+    let envelope = json!({
+        "schema_version": USER_PRODUCTIONS_SCHEMA_VERSION,
+        "series": user_series,
+        "movies": user_movies,
+    });
+    let serialized_json = serde_json::to_string(&envelope)?;
+
+    let temp_path = "res/user_prod_temp.json";
+    let mut file = File::create(temp_path)?;
+    file.write_all(serialized_json.as_bytes())?;
+
+    // Write to a temp file then rename, so a crash mid-write never leaves a truncated user_prod.json.
+    std::fs::rename(temp_path, path)?;
+    Ok(())
+}
+
+pub fn deserialize_user_productions(
+    path: Option<String>,
+) -> Result<(Vec<UserSeries>, Vec<UserMovie>), PersistenceError> {
+    let path = path.unwrap_or_else(|| "res/user_prod.json".into());
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+    let json: Value = serde_json::from_reader(reader)?;
+    let envelope = migrate_user_productions(json)?;
+    Ok((envelope.series, envelope.movies))
+}
+
+// The pre-versioning format was an untagged `{series, movies}` object. Treat a missing
+// `schema_version` as that format and upgrade it in place; reject anything newer than we understand.
+fn migrate_user_productions(mut json: Value) -> Result<UserProductionsEnvelope, PersistenceError> {
+    let schema_version = json["schema_version"].as_u64().unwrap_or(0) as u32;
+    match schema_version {
+        0 => {
+            let series = serde_json::from_value(json["series"].take())?;
+            let movies = serde_json::from_value(json["movies"].take())?;
+            Ok(UserProductionsEnvelope {
+                schema_version: USER_PRODUCTIONS_SCHEMA_VERSION,
+                series,
+                movies,
+            })
+        }
+        USER_PRODUCTIONS_SCHEMA_VERSION => Ok(serde_json::from_value(json)?),
+        other => Err(PersistenceError::UnsupportedSchemaVersion(other)),
+    }
+}
 
 type ProductionId = u32;
 
-#[derive(Default, Copy, Clone)]
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize)]
 pub enum EntryType {
     Movie(ProductionId),
     Series(ProductionId),
@@ -199,29 +521,74 @@ pub struct ListEntry {
     pub name: String,
     pub poster_path: Option<String>, // Shouldn't be an option, should always have a fallback image btw.
     pub rating: f32,
+    // Watch progress in 0.0..=1.0, used by CentralListOrdering::WatchedFirst/UnwatchedFirst.
+    pub progress: f32,
+    // Series don't expose a vote_count, so they're treated as 0 votes and sink toward the mean.
+    pub vote_count: u32,
+    // Derived IMDb-style Bayesian estimate, recomputed once per CentralListOrdering::WeightedRating sort pass.
+    pub weighted_rating: f32,
+    // Used by CentralListOrdering::Favourites, only set via from_user_movie/from_user_series.
+    pub favourite: bool,
+    // Used by CentralListOrdering::WatchTime, only set via from_user_movie/from_user_series.
+    pub watch_time_minutes: u32,
 }
 
 impl ListEntry {
-    pub fn from_movie(movie: &Movie) -> Self {
+    pub fn from_movie(movie: &Movie, preferences: &LocalePreferences) -> Self {
         Self {
             production_id: EntryType::Movie(movie.id),
 
-            name: movie.title.clone(),
+            name: movie.localized_title(preferences).to_string(),
             poster_path: movie.poster_path.clone(),
             rating: movie.vote_average,
+            progress: 0.0,
+            vote_count: movie.vote_count,
+            weighted_rating: movie.vote_average,
+            favourite: false,
+            watch_time_minutes: 0,
         }
     }
 
-    pub fn from_series(series: &Series) -> Self {
+    pub fn from_series(series: &Series, preferences: &LocalePreferences) -> Self {
         Self {
             production_id: EntryType::Series(series.id),
 
-            name: series.name.clone(),
+            name: series.localized_name(preferences).to_string(),
             poster_path: series.poster_path.clone(),
             rating: series.vote_average,
+            progress: 0.0,
+            vote_count: 0,
+            weighted_rating: series.vote_average,
+            favourite: false,
+            watch_time_minutes: 0,
         }
     }
 
+    pub fn from_user_movie(user_movie: &UserMovie, preferences: &LocalePreferences) -> Self {
+        let mut entry = Self::from_movie(&user_movie.movie, preferences);
+        entry.progress = if user_movie.watched { 1.0 } else { 0.0 };
+        entry.favourite = user_movie.favourite;
+        entry.watch_time_minutes = user_movie.watch_time_minutes();
+        entry
+    }
+
+    pub fn from_user_series(user_series: &UserSeries, preferences: &LocalePreferences) -> Self {
+        let mut entry = Self::from_series(&user_series.series, preferences);
+        entry.progress = user_series.completion_percentage();
+        entry.favourite = user_series.favourite;
+        entry.watch_time_minutes = user_series.watch_time_minutes();
+        entry
+    }
+
+    /// Re-derives `name` from the current localized data, e.g. after the user changes
+    /// their preferred locale so the central list can re-render without a full re-fetch.
+    pub fn refresh_localized_name(&mut self, production: &Production, preferences: &LocalePreferences) {
+        self.name = match production {
+            Production::Movie(movie) => movie.localized_title(preferences).to_string(),
+            Production::Series(series) => series.localized_name(preferences).to_string(),
+        };
+    }
+
     pub fn is_selected(&self, entry: &EntryType) -> bool {
         match entry {
             EntryType::Movie(selected_id) => {
@@ -247,12 +614,77 @@ pub enum CentralListOrdering {
     Alphabetic,
     RatingAscending,
     RatingDescending,
+    WatchedFirst,
+    UnwatchedFirst,
+    WeightedRating,
+    Favourites,
+    WatchTime,
 
     // TODO(maybe?):
     // UserRatingAscending,
     // UserRatingDescending,
-    // WatchedFirst,
-    // UnwatchedFirst,
-    // Favourites,
-    // WatchTime,
+}
+
+// Percentile (0.0..=1.0) of vote_count used as the weighted-rating minimum-votes threshold `m`.
+const WEIGHTED_RATING_VOTES_PERCENTILE: f32 = 0.6;
+
+impl CentralListOrdering {
+    /// Orders `entries` in place according to this variant. `WatchedFirst`/`UnwatchedFirst`
+    /// sort on `ListEntry::progress`, which is only populated via `from_user_movie`/`from_user_series`.
+    pub fn sort(&self, entries: &mut [ListEntry]) {
+        match self {
+            CentralListOrdering::UserDefined => {}
+            CentralListOrdering::Alphabetic => entries.sort_by(|a, b| a.name.cmp(&b.name)),
+            CentralListOrdering::RatingAscending => {
+                entries.sort_by(|a, b| a.rating.partial_cmp(&b.rating).unwrap())
+            }
+            CentralListOrdering::RatingDescending => {
+                entries.sort_by(|a, b| b.rating.partial_cmp(&a.rating).unwrap())
+            }
+            CentralListOrdering::WatchedFirst => {
+                entries.sort_by(|a, b| b.progress.partial_cmp(&a.progress).unwrap())
+            }
+            CentralListOrdering::UnwatchedFirst => {
+                entries.sort_by(|a, b| a.progress.partial_cmp(&b.progress).unwrap())
+            }
+            CentralListOrdering::WeightedRating => {
+                compute_weighted_ratings(entries);
+                entries.sort_by(|a, b| b.weighted_rating.partial_cmp(&a.weighted_rating).unwrap())
+            }
+            // Stable sort: pins favourites to the top without reshuffling entries within each group.
+            CentralListOrdering::Favourites => {
+                entries.sort_by_key(|entry| std::cmp::Reverse(entry.favourite))
+            }
+            CentralListOrdering::WatchTime => {
+                entries.sort_by_key(|entry| std::cmp::Reverse(entry.watch_time_minutes))
+            }
+        }
+    }
+}
+
+/// Fills in `ListEntry::weighted_rating` for every entry using the IMDb-style true Bayesian
+/// estimate `WR = (v/(v+m))*R + (m/(v+m))*C`, where `C` is the mean rating across `entries`
+/// and `m` is the `WEIGHTED_RATING_VOTES_PERCENTILE`th percentile of `vote_count`.
+fn compute_weighted_ratings(entries: &mut [ListEntry]) {
+    if entries.is_empty() {
+        return;
+    }
+
+    let mean_rating: f32 =
+        entries.iter().map(|e| e.rating).sum::<f32>() / entries.len() as f32;
+
+    let mut vote_counts: Vec<u32> = entries.iter().map(|e| e.vote_count).collect();
+    vote_counts.sort_unstable();
+    let percentile_idx =
+        ((vote_counts.len() - 1) as f32 * WEIGHTED_RATING_VOTES_PERCENTILE).round() as usize;
+    let min_votes = vote_counts[percentile_idx] as f32;
+
+    for entry in entries.iter_mut() {
+        let v = entry.vote_count as f32;
+        entry.weighted_rating = if v + min_votes == 0.0 {
+            mean_rating
+        } else {
+            (v / (v + min_votes)) * entry.rating + (min_votes / (v + min_votes)) * mean_rating
+        };
+    }
 }
@@ -0,0 +1,355 @@
+use crate::production::{EntryType, Movie, Series, UserMovie, UserSeries};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const VIDEO_EXTENSIONS: [&str; 7] = ["mp4", "mkv", "avi", "mov", "wmv", "flv", "webm"];
+
+// Release-group/quality tags that show up between the title and the rest of a filename.
+// Matched case-insensitively as whole tokens once the filename is split on separators.
+const RELEASE_TAGS: [&str; 20] = [
+    "480p", "720p", "1080p", "2160p", "4k", "hdtv", "web", "webrip", "webdl", "web-dl", "bluray",
+    "brrip", "bdrip", "dvdrip", "x264", "x265", "h264", "h265", "hevc", "aac",
+];
+
+/// A title/year/season/episode breakdown extracted from a release filename.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParsedFilename {
+    pub title: String,
+    pub year: Option<u16>,
+    pub season: Option<u32>,
+    pub episode: Option<u32>,
+}
+
+/// Splits a filename into tokens, drops the extension and everything from the first release
+/// tag onward (including a group name attached to it, as in `x264-GROUP`), and pulls out a
+/// year or `SxxEyy`/`1x03` style marker. A `-GROUP` suffix with no release tag before it is
+/// kept as part of the title.
+pub fn parse_filename(file_name: &str) -> ParsedFilename {
+    let stem = match file_name.rfind('.') {
+        Some(idx) if VIDEO_EXTENSIONS.contains(&&file_name[idx + 1..].to_lowercase()[..]) => {
+            &file_name[..idx]
+        }
+        _ => file_name,
+    };
+
+    let normalized = stem.replace(['.', '_'], " ");
+    let tokens: Vec<&str> = normalized.split_whitespace().collect();
+
+    let mut season = None;
+    let mut episode = None;
+    let mut year = None;
+    let mut title_tokens: Vec<&str> = Vec::new();
+
+    for token in tokens {
+        if let Some((s, e)) = parse_season_episode(token) {
+            season = Some(s);
+            episode = Some(e);
+            // Everything after the season/episode marker is an episode name, not part of the title.
+            break;
+        }
+        if let (None, Some(parsed_year)) = (year, parse_year(token)) {
+            year = Some(parsed_year);
+            continue;
+        }
+        let lower = token.to_lowercase();
+        let is_release_tag = RELEASE_TAGS.contains(&lower.as_str())
+            || lower
+                .split_once('-')
+                .map_or(false, |(head, _)| RELEASE_TAGS.contains(&head));
+        if is_release_tag {
+            // Everything from the first release tag onward is noise, not title.
+            break;
+        }
+        title_tokens.push(token);
+    }
+
+    ParsedFilename {
+        title: title_tokens.join(" ").trim().to_string(),
+        year,
+        season,
+        episode,
+    }
+}
+
+fn parse_year(token: &str) -> Option<u16> {
+    let trimmed = token.trim_start_matches('(').trim_end_matches(')');
+    if trimmed.len() == 4 && trimmed.chars().all(|c| c.is_ascii_digit()) {
+        let year: u16 = trimmed.parse().ok()?;
+        if (1880..=2100).contains(&year) {
+            return Some(year);
+        }
+    }
+    None
+}
+
+// Handles "S01E03", "s1e3" and "1x03" patterns.
+fn parse_season_episode(token: &str) -> Option<(u32, u32)> {
+    let lower = token.to_lowercase();
+    if let Some(stripped) = lower.strip_prefix('s') {
+        let e_idx = stripped.find('e')?;
+        let season: u32 = stripped[..e_idx].parse().ok()?;
+        let episode: u32 = stripped[e_idx + 1..].parse().ok()?;
+        return Some((season, episode));
+    }
+    if let Some(x_idx) = lower.find('x') {
+        let season: u32 = lower[..x_idx].parse().ok()?;
+        let episode: u32 = lower[x_idx + 1..].parse().ok()?;
+        // Reject resolution tokens like "1920x1080": no real season/episode marker needs
+        // a season in the hundreds or an episode number in the thousands.
+        if season >= 100 || episode >= 1000 {
+            return None;
+        }
+        return Some((season, episode));
+    }
+    None
+}
+
+/// A lookup seam into TMDB so the scanner doesn't depend on the concrete HTTP client.
+pub trait ProductionLookup {
+    fn find_movie(&self, title: &str, year: Option<u16>) -> Option<Movie>;
+    fn find_series(&self, title: &str) -> Option<Series>;
+}
+
+/// Fingerprint of a scanned file, used to skip re-parsing/re-matching unchanged files.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FileFingerprint {
+    pub size: u64,
+    pub modified_secs: u64,
+}
+
+impl FileFingerprint {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let modified_secs = metadata
+            .modified()
+            .ok()?
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .ok()?
+            .as_secs();
+        Some(Self {
+            size: metadata.len(),
+            modified_secs,
+        })
+    }
+}
+
+/// Incremental scanner over a user's media directory. Keeps a path -> fingerprint/match
+/// cache so re-running a scan only touches files that were added or modified since last time.
+#[derive(Default)]
+pub struct LibraryScanner {
+    pub root: PathBuf,
+    pub known: HashMap<PathBuf, (FileFingerprint, EntryType)>,
+    pub unmatched: Vec<PathBuf>,
+}
+
+impl LibraryScanner {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            known: HashMap::new(),
+            unmatched: Vec::new(),
+        }
+    }
+
+    /// Walks `root` recursively and parses every unseen or changed video file, matching it
+    /// against TMDB through `lookup`. Matches are reconciled into `series_library`/
+    /// `movie_library` in place: a file matching a `Production` already present (either
+    /// passed in by the caller or added earlier in this same scan) has its season/episode
+    /// merged into that existing entry instead of spawning a duplicate `UserSeries`/
+    /// `UserMovie`. Files that could not be matched are pushed onto `self.unmatched` for
+    /// manual review.
+    pub fn scan(
+        &mut self,
+        lookup: &dyn ProductionLookup,
+        series_library: &mut Vec<UserSeries>,
+        movie_library: &mut Vec<UserMovie>,
+    ) {
+        let root = self.root.clone();
+
+        for path in walk_video_files(&root) {
+            let Some(fingerprint) = FileFingerprint::from_path(&path) else {
+                continue;
+            };
+            if matches!(self.known.get(&path), Some((known_fingerprint, _)) if *known_fingerprint == fingerprint)
+            {
+                continue;
+            }
+
+            let file_name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or_default();
+            let parsed = parse_filename(file_name);
+
+            if parsed.season.is_some() {
+                if let Some(series) = lookup.find_series(&parsed.title) {
+                    self.known
+                        .insert(path.clone(), (fingerprint, EntryType::Series(series.id)));
+                    let series_id = series.id;
+                    let user_series = match series_library
+                        .iter_mut()
+                        .find(|existing| existing.series.id == series_id)
+                    {
+                        Some(existing) => existing,
+                        None => {
+                            series_library.push(UserSeries {
+                                series,
+                                user_rating: 0.0,
+                                note: String::new(),
+                                season_notes: Vec::new(),
+                                favourite: false,
+                                star_rating: None,
+                                watch_log: Vec::new(),
+                            });
+                            series_library.last_mut().expect("just pushed")
+                        }
+                    };
+
+                    // TMDB uses season 0 for specials/extras, which don't fit the 1-based
+                    // season_notes layout below; record the series match but skip sizing.
+                    if let Some(season) = parsed.season.filter(|&season| season >= 1) {
+                        let season = season as usize;
+                        user_series.ensure_seasons(season);
+                        if let Some(season_notes) = user_series.season_notes.get_mut(season - 1) {
+                            season_notes.ensure_episodes(parsed.episode.unwrap_or(1) as usize);
+                        }
+                    }
+                    continue;
+                }
+            } else if let Some(movie) = lookup.find_movie(&parsed.title, parsed.year) {
+                self.known
+                    .insert(path.clone(), (fingerprint, EntryType::Movie(movie.id)));
+                let already_in_library = movie_library.iter().any(|existing| existing.movie.id == movie.id);
+                if !already_in_library {
+                    movie_library.push(UserMovie {
+                        movie,
+                        user_rating: 0.0,
+                        note: String::new(),
+                        watched: false,
+                        favourite: false,
+                        star_rating: None,
+                        watch_log: Vec::new(),
+                    });
+                }
+                continue;
+            }
+
+            // Fingerprint unmatched files too, so an unchanged still-unmatched file is a
+            // no-op on the next scan instead of piling up duplicate review entries.
+            self.known.insert(path.clone(), (fingerprint, EntryType::None));
+            self.unmatched.push(path);
+        }
+    }
+}
+
+fn walk_video_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return files;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            files.extend(walk_video_files(&path));
+            continue;
+        }
+        let is_video = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| VIDEO_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+            .unwrap_or(false);
+        if is_video {
+            files.push(path);
+        }
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_movie_with_year_and_tags() {
+        let parsed = parse_filename("The.Matrix.1999.1080p.BluRay.x264-GROUP.mkv");
+        assert_eq!(parsed.title, "The Matrix");
+        assert_eq!(parsed.year, Some(1999));
+        assert_eq!(parsed.season, None);
+        assert_eq!(parsed.episode, None);
+    }
+
+    #[test]
+    fn parses_sxxexx_pattern() {
+        let parsed = parse_filename("Breaking.Bad.S01E03.720p.WEB.mkv");
+        assert_eq!(parsed.title, "Breaking Bad");
+        assert_eq!(parsed.season, Some(1));
+        assert_eq!(parsed.episode, Some(3));
+    }
+
+    #[test]
+    fn parses_1x03_pattern() {
+        let parsed = parse_filename("Archer 1x03 Some Episode Name.mkv");
+        assert_eq!(parsed.title, "Archer");
+        assert_eq!(parsed.season, Some(1));
+        assert_eq!(parsed.episode, Some(3));
+    }
+
+    #[test]
+    fn does_not_mistake_a_resolution_tag_for_a_season_marker() {
+        let parsed = parse_filename("Some.Movie.2020.1920x1080.BluRay.mkv");
+        assert_eq!(parsed.season, None);
+        assert_eq!(parsed.episode, None);
+    }
+
+    struct StubLookup;
+
+    impl ProductionLookup for StubLookup {
+        fn find_movie(&self, _title: &str, _year: Option<u16>) -> Option<Movie> {
+            None
+        }
+
+        fn find_series(&self, title: &str) -> Option<Series> {
+            Some(Series {
+                id: 1,
+                name: title.to_string(),
+                ..Default::default()
+            })
+        }
+    }
+
+    fn scan_dir_with_files(dir_name: &str, file_names: &[&str]) -> (Vec<UserSeries>, LibraryScanner) {
+        let root = std::env::temp_dir().join(format!("moviesdesk-scanner-test-{}", dir_name));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        for file_name in file_names {
+            std::fs::write(root.join(file_name), b"").unwrap();
+        }
+
+        let mut scanner = LibraryScanner::new(root.clone());
+        let mut series_library = Vec::new();
+        let mut movie_library = Vec::new();
+        scanner.scan(&StubLookup, &mut series_library, &mut movie_library);
+
+        std::fs::remove_dir_all(&root).unwrap();
+        (series_library, scanner)
+    }
+
+    #[test]
+    fn merges_episodes_of_the_same_series_into_one_entry() {
+        let (series_library, _scanner) = scan_dir_with_files(
+            "dedupe",
+            &["Breaking.Bad.S01E01.mkv", "Breaking.Bad.S01E02.mkv"],
+        );
+        assert_eq!(series_library.len(), 1);
+        assert_eq!(series_library[0].season_notes[0].episode_notes.len(), 2);
+    }
+
+    #[test]
+    fn special_episode_season_zero_does_not_panic() {
+        let (series_library, scanner) = scan_dir_with_files("specials", &["Breaking.Bad.S00E01.mkv"]);
+        assert_eq!(series_library.len(), 1);
+        assert!(series_library[0].season_notes.is_empty());
+        assert!(scanner.unmatched.is_empty());
+    }
+}